@@ -7,54 +7,313 @@
 use anyhow::{Context, Result, anyhow};
 
 use std::{
+    collections::BTreeSet,
     env,
     ffi::{CStr, CString},
-    io::{self, BufRead},
+    fs,
+    io::{self, BufRead, Read},
     os::raw::{c_char, c_int, c_uint, c_void},
     process, ptr,
+    sync::Arc,
+    thread,
 };
 
 // Hyperscan FFI bindings
 use hyperscan_sys as hs;
+// Chimera FFI bindings (Hyperscan + PCRE hybrid engine, for full PCRE syntax and captures).
+use chimera_sys as ch;
+
+// Parsed command-line configuration.
+// - patterns: One or more regex patterns to compile together, in declaration order.
+//   Pattern IDs handed to Hyperscan are simply the index into this vector.
+// - show_ids: Whether to prefix matching lines with the IDs of the patterns that fired.
+// - stream: Whether to scan stdin as one continuous stream (HS_MODE_STREAM, plus a SOM
+//   horizon mode since matches report start-of-match offsets) instead of scanning each
+//   line independently, so patterns may match across line boundaries.
+// - pcre: Whether to match via the Chimera hybrid engine instead of pure Hyperscan, for
+//   full PCRE syntax (backreferences, lookbehind, capture groups) at some speed cost.
+// - replace: With `--pcre`, a template such as "$1-$2" whose `$N` placeholders are
+//   substituted with capture group N from each match and printed instead of the line.
+// - threads: Number of worker threads to scan lines with in block mode. Each thread gets
+//   its own cloned scratch; the shared database is read-only once compiled.
+// - only_matching: Print just the matched substring instead of the whole line. In the
+//   pure Hyperscan path this uses the match span reported via HS_FLAG_SOMATCH.
+// - byte_offset: Prefix output with the 0-based byte offset of the first match (with
+//   --only-matching, of each printed match).
+// - column: Prefix output with the 1-based column of the first match (with
+//   --only-matching, of each printed match). This is a byte
+//   offset, not a character count, so it can diverge from the visual column on lines
+//   with multibyte UTF-8 characters before the match.
+// - compile_opts: Hyperscan compile flags and logical combination, set via -i/-s/
+//   --single-match/--combination.
+struct Args {
+    patterns: Vec<String>,
+    show_ids: bool,
+    stream: bool,
+    pcre: bool,
+    only_matching: bool,
+    replace: Option<String>,
+    threads: usize,
+    byte_offset: bool,
+    column: bool,
+    compile_opts: CompileOptions,
+}
+
+// Extra Hyperscan compile-time behaviour controlled from the CLI.
+// - caseless/dotall/single_match map directly to their Hyperscan compile flags and apply
+//   to every pattern.
+// - combination: an optional Hyperscan 5 logical combination expression (e.g.
+//   "0 AND (1 OR 2)", referencing patterns by their 0-based `-e` index), compiled as one
+//   extra pattern with HS_FLAG_COMBINATION. When set, every component pattern also gets
+//   HS_FLAG_QUIET so only the combination's own result is reported, and the combination
+//   itself has no meaningful start-of-match offset; parse_args rejects pairing this with
+//   --only-matching/--byte-offset/--column.
+#[derive(Default, Clone)]
+struct CompileOptions {
+    caseless: bool,
+    dotall: bool,
+    single_match: bool,
+    combination: Option<String>,
+}
+
+impl CompileOptions {
+    // The Hyperscan compile flags applied to every component pattern (not the
+    // combination expression itself, which always compiles with HS_FLAG_COMBINATION).
+    fn component_flags(&self) -> c_uint {
+        // A pattern quieted for --combination never reports its own match, so start-of-
+        // match offsets for it would go unused; skip SOMATCH in that case.
+        let mut flags = if self.combination.is_some() {
+            hs::HS_FLAG_QUIET
+        } else {
+            hs::HS_FLAG_SOMATCH
+        };
+        if self.caseless {
+            flags |= hs::HS_FLAG_CASELESS;
+        }
+        if self.dotall {
+            flags |= hs::HS_FLAG_DOTALL;
+        }
+        if self.single_match {
+            flags |= hs::HS_FLAG_SINGLEMATCH;
+        }
+        flags
+    }
+
+    // The Chimera compile flags applied to every pattern under --pcre. Chimera has no
+    // equivalent of HS_FLAG_SOMATCH/QUIET/SINGLEMATCH or combinations, so only
+    // caseless/dotall carry over; parse_args rejects the rest alongside --pcre.
+    fn ch_component_flags(&self) -> c_uint {
+        let mut flags = 0;
+        if self.caseless {
+            flags |= ch::CH_FLAG_CASELESS;
+        }
+        if self.dotall {
+            flags |= ch::CH_FLAG_DOTALL;
+        }
+        flags
+    }
+}
+
+// Parse command-line arguments.
+// Supports repeated `-e`/`--regexp PATTERN` flags, a `-f FILE` flag that reads one
+// pattern per (non-empty) line, a bare positional pattern for backward compatibility,
+// `--ids` to prefix output with the matching pattern IDs, `--stream` to scan stdin as one
+// continuous stream instead of line by line, `--pcre` to match via Chimera instead of
+// pure Hyperscan, `--only-matching`/`--replace TEMPLATE`/`--byte-offset`/`--column` to
+// control match output, `--threads N` to scan block-mode lines across N worker threads,
+// `-i`/`-s`/`--dotall`/`--single-match` for Hyperscan compile flags, and `--combination`
+// for a Hyperscan 5 logical combination expression over the `-e` patterns. Rejects
+// `--combination` paired with `--only-matching`/`--byte-offset`/`--column`, since a
+// combination match has no meaningful start-of-match offset, `--pcre` paired with
+// Hyperscan-only features it can't honor, and any unrecognized `-`-prefixed token
+// (rather than silently compiling a mistyped flag as a pattern).
+fn parse_args() -> Result<Args> {
+    parse_args_from(env::args().skip(1))
+}
+
+// The argument-parsing logic behind `parse_args`, taking an arbitrary iterator of
+// tokens instead of `env::args()` so it can be exercised directly in tests.
+fn parse_args_from(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let mut patterns = Vec::new();
+    let mut show_ids = false;
+    let mut stream = false;
+    let mut pcre = false;
+    let mut only_matching = false;
+    let mut replace = None;
+    let mut threads = 1usize;
+    let mut byte_offset = false;
+    let mut column = false;
+    let mut compile_opts = CompileOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-e" | "--regexp" => {
+                let pattern = args
+                    .next()
+                    .ok_or_else(|| anyhow!("{arg} requires a pattern argument"))?;
+                patterns.push(pattern);
+            }
+            "-f" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("-f requires a file argument"))?;
+                let contents =
+                    fs::read_to_string(&path).with_context(|| format!("read patterns file {path}"))?;
+                patterns.extend(contents.lines().map(str::to_owned).filter(|l| !l.is_empty()));
+            }
+            "--ids" => show_ids = true,
+            "--stream" => stream = true,
+            "-i" => compile_opts.caseless = true,
+            "-s" | "--dotall" => compile_opts.dotall = true,
+            "--single-match" => compile_opts.single_match = true,
+            "--combination" => {
+                compile_opts.combination = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--combination requires an expression argument"))?,
+                );
+            }
+            "--pcre" => pcre = true,
+            "--only-matching" => only_matching = true,
+            "--byte-offset" => byte_offset = true,
+            "--column" => column = true,
+            "--replace" => {
+                replace = Some(
+                    args.next()
+                        .ok_or_else(|| anyhow!("--replace requires a template argument"))?,
+                );
+            }
+            "--threads" => {
+                let count = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--threads requires a number argument"))?;
+                threads = count
+                    .parse()
+                    .map_err(|_| anyhow!("--threads value must be a positive integer"))?;
+                if threads == 0 {
+                    return Err(anyhow!("--threads value must be at least 1"));
+                }
+            }
+            other if other.starts_with('-') && other != "-" => {
+                return Err(anyhow!("unknown flag: {other}"));
+            }
+            other => patterns.push(other.to_owned()),
+        }
+    }
+
+    // Under --combination, component patterns are quieted (HS_FLAG_QUIET, no
+    // HS_FLAG_SOMATCH) so only the combination expression's own result is reported, and
+    // that expression itself has no real start-of-match offset. Location/only-matching
+    // output would therefore be meaningless, so reject the combination up front.
+    if compile_opts.combination.is_some() && (only_matching || byte_offset || column) {
+        return Err(anyhow!(
+            "--combination cannot be used with --only-matching, --byte-offset, or --column"
+        ));
+    }
+
+    // Chimera has no equivalent of HS_FLAG_SINGLEMATCH, logical combinations, streaming
+    // mode, or the block-mode worker threads, so silently ignoring them under --pcre
+    // would be a correctness trap; reject the combination instead.
+    if pcre {
+        if compile_opts.single_match {
+            return Err(anyhow!("--pcre cannot be used with --single-match"));
+        }
+        if compile_opts.combination.is_some() {
+            return Err(anyhow!("--pcre cannot be used with --combination"));
+        }
+        if stream {
+            return Err(anyhow!("--pcre cannot be used with --stream"));
+        }
+        if threads != 1 {
+            return Err(anyhow!("--pcre cannot be used with --threads"));
+        }
+    }
+
+    Ok(Args {
+        patterns,
+        show_ids,
+        stream,
+        pcre,
+        only_matching,
+        replace,
+        threads,
+        byte_offset,
+        column,
+        compile_opts,
+    })
+}
+
+// Context passed to `on_match` while scanning a single line in block mode.
+// Collects the (deduplicated) set of pattern IDs that matched anywhere in the line, plus
+// every (from, to) match span, kept for --only-matching/--byte-offset/--column output.
+#[derive(Default)]
+struct LineMatches {
+    ids: Vec<u32>,
+    spans: Vec<(u64, u64)>,
+}
 
 // Callback invoked by Hyperscan on a match.
 // Parameters:
 // - id: The pattern ID that matched.
-// - from: The start offset of the match.
+// - from: The start offset of the match (meaningful because patterns compile with
+//   HS_FLAG_SOMATCH).
 // - to: The end offset of the match.
-// - flags: Match flags.W
+// - flags: Match flags.
 // - ctx: User-defined context pointer.
 // Returns 0 to continue scanning, non-zero to stop.
-extern "C" fn on_match(
-    _id: c_uint,
-    _from: u64,
-    _to: u64,
-    _flags: c_uint,
-    ctx: *mut c_void,
-) -> c_int {
+extern "C" fn on_match(id: c_uint, from: u64, to: u64, _flags: c_uint, ctx: *mut c_void) -> c_int {
     unsafe {
-        let matched = &mut *(ctx as *mut bool);
-        *matched = true;
+        let matches = &mut *(ctx as *mut LineMatches);
+        if !matches.ids.contains(&id) {
+            matches.ids.push(id);
+        }
+        matches.spans.push((from, to));
     }
 
     0 // 0 → continue scanning
 }
 
-// Compile a regex pattern into a Hyperscan database.
-// - pattern: The regex pattern to compile.
+// Compile one or more regex patterns into a single Hyperscan database.
+// - patterns: The regex patterns to compile; each pattern's index becomes its ID.
+// - mode: The Hyperscan scanning mode to compile for (e.g. HS_MODE_BLOCK, HS_MODE_STREAM).
+// - opts: Compile flags applied to every pattern, plus an optional logical combination
+//   expression compiled alongside them as one extra pattern.
 // Returns a pointer to the database on success.
-fn compile_database(pattern: &str) -> Result<*mut hs::hs_database_t> {
-    let pat_c = CString::new(pattern).map_err(|_| anyhow!("pattern contains interior NUL"))?;
+fn compile_database(
+    patterns: &[String],
+    mode: c_uint,
+    opts: &CompileOptions,
+) -> Result<*mut hs::hs_database_t> {
+    // HS_FLAG_SOMATCH (start-of-match) makes the `from` offset on_match receives the true
+    // start of the match, rather than an undefined value.
+    let mut flags: Vec<c_uint> = vec![opts.component_flags(); patterns.len()];
+    let mut all_patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+
+    // A logical combination is compiled as one extra pattern referencing the others by
+    // their `-e` index, with HS_FLAG_COMBINATION instead of the component flags above.
+    if let Some(combination) = &opts.combination {
+        all_patterns.push(combination.as_str());
+        flags.push(hs::HS_FLAG_COMBINATION);
+    }
+
+    let pat_cstrings: Vec<CString> = all_patterns
+        .iter()
+        .map(|p| CString::new(*p).map_err(|_| anyhow!("pattern contains interior NUL")))
+        .collect::<Result<_>>()?;
+
+    let expressions: Vec<*const c_char> = pat_cstrings.iter().map(|p| p.as_ptr()).collect();
+    let ids: Vec<c_uint> = (0..all_patterns.len() as c_uint).collect();
 
     let mut db: *mut hs::hs_database_t = ptr::null_mut();
     let mut err: *mut hs::hs_compile_error_t = ptr::null_mut();
 
-    // Calls Hyperscan to compile the pattern.
+    // Calls Hyperscan to compile all patterns together, keyed by `ids`.
     let result = unsafe {
-        hs::hs_compile(
-            pat_c.as_ptr(),
-            0,
-            hs::HS_MODE_BLOCK,
+        hs::hs_compile_multi(
+            expressions.as_ptr(),
+            flags.as_ptr(),
+            ids.as_ptr(),
+            all_patterns.len() as c_uint,
+            mode,
             ptr::null(),
             &mut db,
             &mut err,
@@ -100,17 +359,26 @@ fn alloc_scratch(db: *mut hs::hs_database_t) -> Result<*mut hs::hs_scratch_t> {
     Ok(scratch)
 }
 
+// The result of scanning a single line: which patterns matched, and where. Hyperscan may
+// report overlapping or out-of-order matches, so `spans` is sorted and deduplicated.
+struct LineScan {
+    ids: Vec<u32>,
+    spans: Vec<(u64, u64)>,
+}
+
+impl LineScan {
+    fn is_match(&self) -> bool {
+        !self.spans.is_empty()
+    }
+}
+
 // Scan a line of text using the given Hyperscan database and scratch space.
 // - db: Pointer to the compiled Hyperscan database.
 // - scratch: Pointer to the allocated scratch space.
 // - line: The line of text to scan.
-// Returns true if a match was found.
-fn scan_line(
-    db: *mut hs::hs_database_t,
-    scratch: *mut hs::hs_scratch_t,
-    line: &str,
-) -> Result<bool> {
-    let mut matched: bool = false;
+// Returns the matching pattern IDs and match spans found in the line.
+fn scan_line(db: *mut hs::hs_database_t, scratch: *mut hs::hs_scratch_t, line: &str) -> Result<LineScan> {
+    let mut matches = LineMatches::default();
 
     let result: i32 = unsafe {
         hs::hs_scan(
@@ -120,7 +388,7 @@ fn scan_line(
             0,
             scratch,
             Some(on_match),
-            (&mut matched as *mut bool).cast::<c_void>(),
+            (&mut matches as *mut LineMatches).cast::<c_void>(),
         )
     };
 
@@ -128,37 +396,598 @@ fn scan_line(
         return Err(anyhow!("hs_scan failed (rc={result})"));
     }
 
-    Ok(matched)
+    matches.ids.sort_unstable();
+    matches.spans.sort_unstable();
+    matches.spans.dedup();
+    Ok(LineScan {
+        ids: matches.ids,
+        spans: matches.spans,
+    })
+}
+
+// Output formatting switches shared by the serial and `--threads` scanning paths.
+#[derive(Clone, Copy)]
+struct OutputOptions {
+    show_ids: bool,
+    only_matching: bool,
+    byte_offset: bool,
+    column: bool,
+}
+
+impl OutputOptions {
+    // Format a matched line for printing, honouring `show_ids`, `only_matching`,
+    // `byte_offset`, and `column`. Returns one output line per requested match (several
+    // with `only_matching`, otherwise exactly one), or nothing if `scan` didn't match.
+    fn format(&self, line: &str, scan: &LineScan) -> Vec<String> {
+        if !scan.is_match() {
+            return Vec::new();
+        }
+
+        let id_prefix = || {
+            if self.show_ids {
+                let labels: Vec<String> = scan.ids.iter().map(u32::to_string).collect();
+                format!("[{}] ", labels.join(","))
+            } else {
+                String::new()
+            }
+        };
+
+        let location_prefix = |from: u64| {
+            let mut prefix = String::new();
+            if self.byte_offset {
+                prefix.push_str(&format!("{from}:"));
+            }
+            if self.column {
+                // NOTE: this is a 1-based *byte* offset, not a character column, so it
+                // diverges from the visual column on lines with multibyte UTF-8
+                // characters before the match.
+                prefix.push_str(&format!("{}:", from as usize + 1));
+            }
+            prefix
+        };
+
+        if self.only_matching {
+            let matched: Vec<String> = scan
+                .spans
+                .iter()
+                // `from`/`to` are Hyperscan byte offsets, which can land inside a
+                // multibyte UTF-8 char; skip a span that isn't on a char boundary rather
+                // than panicking on valid input (e.g. `--only-matching -e .` on "café").
+                .filter_map(|&(from, to)| {
+                    line.get(from as usize..to as usize)
+                        .map(|matched| format!("{}{}{}", id_prefix(), location_prefix(from), matched))
+                })
+                .collect();
+
+            if matched.is_empty() {
+                // Every span straddled a char boundary; fall back to the whole line
+                // rather than silently dropping a line that did match.
+                let (from, _) = scan.spans[0];
+                vec![format!("{}{}{line}", id_prefix(), location_prefix(from))]
+            } else {
+                matched
+            }
+        } else {
+            let (from, _) = scan.spans[0];
+            vec![format!("{}{}{line}", id_prefix(), location_prefix(from))]
+        }
+    }
+}
+
+// Clone scratch space so it can be handed to a separate worker thread.
+// Hyperscan scratch is not shareable across concurrent scans, but it can be cheaply
+// duplicated from an already-allocated scratch of the same size.
+// - scratch: Pointer to the scratch space to clone.
+// Returns a pointer to the new scratch space on success.
+fn clone_scratch(scratch: *mut hs::hs_scratch_t) -> Result<*mut hs::hs_scratch_t> {
+    let mut clone: *mut hs::hs_scratch_t = ptr::null_mut();
+
+    let result = unsafe { hs::hs_clone_scratch(scratch, &mut clone) };
+
+    if result != hs::HS_SUCCESS as i32 {
+        return Err(anyhow!(
+            "Failed: Unable to clone Hyperscan scratch (rc = {result})"
+        ));
+    }
+
+    Ok(clone)
+}
+
+// Wraps a Hyperscan database pointer so it can be shared across worker threads.
+// Once compiled, a database is read-only and Hyperscan documents it as safe to use
+// concurrently from multiple threads, each with its own scratch space.
+struct SharedDatabase(*mut hs::hs_database_t);
+unsafe impl Send for SharedDatabase {}
+unsafe impl Sync for SharedDatabase {}
+
+// Wraps a cloned scratch pointer so it can be moved into the worker thread that owns it
+// exclusively; unlike the database, each worker's scratch is never touched by any other
+// thread.
+struct OwnedScratch(*mut hs::hs_scratch_t);
+unsafe impl Send for OwnedScratch {}
+
+// Scan a batch of lines across `num_threads` worker threads, each with its own cloned
+// scratch, and reassemble the matching lines in their original order.
+// - db: Pointer to the compiled Hyperscan database, shared read-only across threads.
+// - base_scratch: Scratch space to clone once per worker thread.
+// - lines: The lines to scan, in input order.
+// - num_threads: How many worker threads to split `lines` across.
+// - opts: Output formatting options, forwarded to `OutputOptions::format` for each line.
+// Returns the matching lines, formatted as `main`'s serial path would, in input order.
+fn scan_lines_parallel(
+    db: *mut hs::hs_database_t,
+    base_scratch: *mut hs::hs_scratch_t,
+    lines: Vec<String>,
+    num_threads: usize,
+    opts: OutputOptions,
+) -> Result<Vec<String>> {
+    let shared_db = Arc::new(SharedDatabase(db));
+    let lines = Arc::new(lines);
+    let chunk_size = lines.len().div_ceil(num_threads).max(1);
+
+    let mut handles = Vec::new();
+    for chunk_start in (0..lines.len()).step_by(chunk_size) {
+        let shared_db = Arc::clone(&shared_db);
+        let lines = Arc::clone(&lines);
+        let chunk_end = (chunk_start + chunk_size).min(lines.len());
+        let scratch = OwnedScratch(clone_scratch(base_scratch)?);
+
+        handles.push(thread::spawn(
+            move || -> Result<Vec<(usize, Vec<String>)>> {
+                let scratch = scratch;
+                let mut out = Vec::with_capacity(chunk_end - chunk_start);
+                for idx in chunk_start..chunk_end {
+                    let line = &lines[idx];
+                    let scan = scan_line(shared_db.0, scratch.0, line)?;
+                    let printed = opts.format(line, &scan);
+                    out.push((idx, printed));
+                }
+
+                unsafe { hs::hs_free_scratch(scratch.0) };
+                Ok(out)
+            },
+        ));
+    }
+
+    let mut results: Vec<Vec<String>> = vec![Vec::new(); lines.len()];
+    for handle in handles {
+        let batch = handle
+            .join()
+            .map_err(|_| anyhow!("worker thread panicked"))??;
+        for (idx, printed) in batch {
+            results[idx] = printed;
+        }
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+// Context passed to `on_stream_match` for the lifetime of a streaming scan.
+// Unlike block mode, offsets are positions in the whole stream rather than a single
+// line, so we accumulate every (from, to) span and resolve them to lines afterwards.
+#[derive(Default)]
+struct StreamMatches {
+    spans: Vec<(u64, u64)>,
+}
+
+// Callback invoked by Hyperscan on a match while scanning a stream.
+// Parameters mirror `on_match`; see its doc comment for details.
+extern "C" fn on_stream_match(
+    _id: c_uint,
+    from: u64,
+    to: u64,
+    _flags: c_uint,
+    ctx: *mut c_void,
+) -> c_int {
+    unsafe {
+        let matches = &mut *(ctx as *mut StreamMatches);
+        matches.spans.push((from, to));
+    }
+
+    0 // 0 → continue scanning
+}
+
+// The chunk size used to feed stdin into `hs_scan_stream`. Arbitrary but small enough
+// to demonstrate that a stream can be assembled from several separate scan calls.
+const STREAM_CHUNK_SIZE: usize = 4096;
+
+// Scan an entire input buffer as one Hyperscan stream, so patterns may match across
+// what would otherwise be line boundaries.
+// - db: Pointer to a database compiled with HS_MODE_STREAM.
+// - scratch: Pointer to the allocated scratch space.
+// - input: The full buffer to scan, fed to Hyperscan in fixed-size chunks.
+// Returns every (from, to) match span found, as offsets into `input`.
+fn scan_stream(
+    db: *mut hs::hs_database_t,
+    scratch: *mut hs::hs_scratch_t,
+    input: &str,
+) -> Result<Vec<(u64, u64)>> {
+    let mut stream: *mut hs::hs_stream_t = ptr::null_mut();
+
+    let result = unsafe { hs::hs_open_stream(db, 0, &mut stream) };
+    if result != hs::HS_SUCCESS as i32 {
+        return Err(anyhow!("hs_open_stream failed (rc={result})"));
+    }
+
+    let mut matches = StreamMatches::default();
+    let ctx = (&mut matches as *mut StreamMatches).cast::<c_void>();
+
+    for chunk in input.as_bytes().chunks(STREAM_CHUNK_SIZE) {
+        let result = unsafe {
+            hs::hs_scan_stream(
+                stream,
+                chunk.as_ptr() as *const c_char,
+                chunk.len() as u32,
+                0,
+                scratch,
+                Some(on_stream_match),
+                ctx,
+            )
+        };
+
+        if result != hs::HS_SUCCESS as i32 {
+            // Best-effort cleanup before reporting the failure.
+            unsafe { hs::hs_close_stream(stream, scratch, None, ptr::null_mut()) };
+            return Err(anyhow!("hs_scan_stream failed (rc={result})"));
+        }
+    }
+
+    // Closing the stream can itself report matches still pending in Hyperscan's
+    // internal buffers, so it uses the same callback and context.
+    let result = unsafe { hs::hs_close_stream(stream, scratch, Some(on_stream_match), ctx) };
+    if result != hs::HS_SUCCESS as i32 {
+        return Err(anyhow!("hs_close_stream failed (rc={result})"));
+    }
+
+    matches.spans.sort_unstable();
+    matches.spans.dedup();
+    Ok(matches.spans)
+}
+
+// Map global stream offsets back to the lines containing them.
+// - input: The full buffer that was fed to `scan_stream`.
+// - spans: Match spans as offsets into `input`.
+// Returns the matching lines, stripped of trailing '\r', in their original order. A span
+// that crosses a newline (e.g. "foo\n.*bar") yields every line it overlaps, not just the
+// one containing `from`.
+fn lines_for_spans(input: &str, spans: &[(u64, u64)]) -> Vec<String> {
+    let lines: Vec<&str> = input.split('\n').collect();
+    let mut line_starts = Vec::with_capacity(lines.len());
+    let mut offset = 0u64;
+    for line in &lines {
+        line_starts.push(offset);
+        offset += line.len() as u64 + 1; // +1 for the newline consumed by split
+    }
+
+    let mut matched_lines = BTreeSet::new();
+    for &(from, to) in spans {
+        // Find the last line starting at or before `from` and at or before the span's
+        // last byte (`to` is exclusive).
+        let last = to.saturating_sub(1).max(from);
+        let from_idx = line_starts.partition_point(|&start| start <= from).saturating_sub(1);
+        let to_idx = line_starts.partition_point(|&start| start <= last).saturating_sub(1);
+        for idx in from_idx..=to_idx {
+            matched_lines.insert(idx);
+        }
+    }
+
+    matched_lines
+        .into_iter()
+        .filter_map(|idx| lines.get(idx))
+        .map(|line| line.strip_suffix('\r').unwrap_or(line).to_owned())
+        .collect()
+}
+
+// One match reported by Chimera: every capture group's span, where index 0 is always
+// the whole match. `None` means that group did not participate in this particular match.
+struct ChimeraMatch {
+    groups: Vec<Option<(u64, u64)>>,
+}
+
+// Context passed to `on_chimera_match` while scanning a single line.
+// Chimera can report several matches per line, so every match event's capture groups
+// are collected in order.
+#[derive(Default)]
+struct ChimeraLineMatches {
+    matches: Vec<ChimeraMatch>,
+}
+
+// Callback invoked by Chimera on a match.
+// Parameters:
+// - id: The pattern ID that matched.
+// - from/to: The start/end offset of the overall match (capture group 0).
+// - flags: Match flags.
+// - size: The number of entries in `captured`.
+// - captured: The capture group array; `captured[0]` is the overall match.
+// - ctx: User-defined context pointer.
+// Returns CH_CALLBACK_CONTINUE to keep scanning.
+extern "C" fn on_chimera_match(
+    _id: c_uint,
+    _from: u64,
+    _to: u64,
+    _flags: c_uint,
+    size: c_uint,
+    captured: *const ch::ch_capture_t,
+    ctx: *mut c_void,
+) -> c_int {
+    unsafe {
+        let groups = if captured.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(captured, size as usize)
+                .iter()
+                .map(|c| (c.matched != 0).then_some((c.from, c.to)))
+                .collect()
+        };
+
+        let matches = &mut *(ctx as *mut ChimeraLineMatches);
+        matches.matches.push(ChimeraMatch { groups });
+    }
+
+    ch::CH_CALLBACK_CONTINUE as c_int
+}
+
+// Callback invoked by Chimera when a pattern fails during matching (e.g. it exceeds
+// PCRE's match limits). We skip just that pattern and let the rest of the scan continue.
+extern "C" fn on_chimera_error(
+    _error: ch::ch_error_event_t,
+    id: c_uint,
+    _info: *mut c_void,
+    _ctx: *mut c_void,
+) -> c_int {
+    eprintln!("warning: chimera pattern {id} failed during matching, skipping it");
+    ch::CH_CALLBACK_SKIP_PATTERN as c_int
+}
+
+// Compile one or more regex patterns into a single Chimera database, using full PCRE
+// syntax and reporting capture groups.
+// - patterns: The regex patterns to compile; each pattern's index becomes its ID.
+// - opts: Compile flags applied to every pattern (only caseless/dotall carry over to
+//   Chimera; see `CompileOptions::ch_component_flags`).
+// Returns a pointer to the database on success.
+fn compile_chimera_database(
+    patterns: &[String],
+    opts: &CompileOptions,
+) -> Result<*mut ch::ch_database_t> {
+    let pat_cstrings: Vec<CString> = patterns
+        .iter()
+        .map(|p| CString::new(p.as_str()).map_err(|_| anyhow!("pattern contains interior NUL")))
+        .collect::<Result<_>>()?;
+
+    let expressions: Vec<*const c_char> = pat_cstrings.iter().map(|p| p.as_ptr()).collect();
+    let flags: Vec<c_uint> = vec![opts.ch_component_flags(); patterns.len()];
+    let ids: Vec<c_uint> = (0..patterns.len() as c_uint).collect();
+
+    let mut db: *mut ch::ch_database_t = ptr::null_mut();
+    let mut err: *mut ch::ch_compile_error_t = ptr::null_mut();
+
+    let result = unsafe {
+        ch::ch_compile_multi(
+            expressions.as_ptr(),
+            flags.as_ptr(),
+            ids.as_ptr(),
+            patterns.len() as c_uint,
+            ch::CH_MODE_GROUPS,
+            ptr::null(),
+            &mut db,
+            &mut err,
+        )
+    };
+
+    if result != ch::CH_SUCCESS as i32 {
+        let msg = unsafe {
+            if !err.is_null() && !(*err).message.is_null() {
+                CStr::from_ptr((*err).message)
+                    .to_string_lossy()
+                    .into_owned()
+            } else {
+                "Unknown compile error".to_string()
+            }
+        };
+
+        unsafe { ch::ch_free_compile_error(err) };
+
+        return Err(anyhow!("Chimera compile error: {msg}"));
+    }
+
+    Ok(db)
+}
+
+// Allocate scratch space for Chimera scanning.
+// - db: Pointer to the compiled Chimera database.
+// Returns a pointer to the scratch space on success.
+fn alloc_chimera_scratch(db: *mut ch::ch_database_t) -> Result<*mut ch::ch_scratch_t> {
+    let mut scratch: *mut ch::ch_scratch_t = ptr::null_mut();
+
+    let result = unsafe { ch::ch_alloc_scratch(db, &mut scratch) };
+
+    if result != ch::CH_SUCCESS as i32 {
+        return Err(anyhow!(
+            "Failed: Unable to allocate Chimera scratch (rc = {result})"
+        ));
+    }
+
+    Ok(scratch)
+}
+
+// Scan a line of text using the given Chimera database and scratch space.
+// - db: Pointer to the compiled Chimera database.
+// - scratch: Pointer to the allocated scratch space.
+// - line: The line of text to scan.
+// Returns every match found, each carrying its capture group spans.
+fn scan_line_chimera(
+    db: *mut ch::ch_database_t,
+    scratch: *mut ch::ch_scratch_t,
+    line: &str,
+) -> Result<Vec<ChimeraMatch>> {
+    let mut matches = ChimeraLineMatches::default();
+
+    let result: i32 = unsafe {
+        ch::ch_scan(
+            db,
+            line.as_ptr() as *const c_char,
+            line.len() as u32,
+            0,
+            scratch,
+            Some(on_chimera_match),
+            Some(on_chimera_error),
+            (&mut matches as *mut ChimeraLineMatches).cast::<c_void>(),
+        )
+    };
+
+    if result != ch::CH_SUCCESS as i32 {
+        return Err(anyhow!("ch_scan failed (rc={result})"));
+    }
+
+    Ok(matches.matches)
+}
+
+// Substitute `$N` placeholders in a `--replace` template with capture group N's text.
+// `$0` is the whole match; a placeholder referring to a group that did not participate,
+// or that does not exist, is replaced with an empty string.
+fn apply_replace_template(template: &str, line: &str, groups: &[Option<(u64, u64)>]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        let mut digits = String::new();
+        while let Some(d) = chars.peek() {
+            if d.is_ascii_digit() {
+                digits.push(*d);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+
+        if digits.is_empty() {
+            out.push('$');
+            continue;
+        }
+
+        if let Ok(idx) = digits.parse::<usize>() {
+            if let Some(Some((from, to))) = groups.get(idx) {
+                // Chimera reports byte offsets, which can land inside a multibyte char;
+                // silently drop a group whose span isn't on a char boundary rather than
+                // panicking on valid UTF-8 input.
+                if let Some(text) = line.get(*from as usize..*to as usize) {
+                    out.push_str(text);
+                }
+            }
+        }
+    }
+
+    out
 }
 
 fn main() -> Result<()> {
-    // Get the regex pattern from command-line arguments.
-    // Pattern is expected as the first argument.
-    // If not provided, print usage and exit.
+    // Parse one or more regex patterns from `-e`/`--regexp`, `-f FILE`, or a bare
+    // positional argument. If none are provided, print usage and exit.
     // Example usage:
     // ```type emails.txt | minigrep_hw.exe "^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$"```
-    let pattern: String = match env::args().nth(1) {
-        Some(p) => p,
-        None => {
-            eprintln!(
-                "Usage:\n  type file.txt | minigrep_hw.exe \"<regex>\"\nExample:\n  type emails.txt | minigrep_hw.exe \"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\\.[a-zA-Z]{{2,}}$\""
-            );
-            process::exit(1);
+    // ```type log.txt | minigrep_hw.exe -e ERROR -e WARN --ids```
+    let args = parse_args().context("parse arguments")?;
+
+    if args.patterns.is_empty() {
+        eprintln!(
+            "Usage:\n  type file.txt | minigrep_hw.exe \"<regex>\"\n  type file.txt | minigrep_hw.exe -e <regex> [-e <regex> ...] [-f patterns.txt] [-i] [-s|--dotall] [--single-match] [--ids] [--stream] [--threads N] [--only-matching] [--byte-offset] [--column]\n  type file.txt | minigrep_hw.exe -e <regex> -e <regex> -e <regex> --combination \"0 AND (1 OR 2)\"\n  type file.txt | minigrep_hw.exe --pcre -e <regex> [--only-matching | --replace <template>]\nExample:\n  type emails.txt | minigrep_hw.exe \"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\\.[a-zA-Z]{{2,}}$\""
+        );
+        process::exit(1);
+    }
+
+    if args.pcre {
+        let database = compile_chimera_database(&args.patterns, &args.compile_opts)
+            .context("compile patterns")?;
+        let scratch = alloc_chimera_scratch(database).context("alloc scratch")?;
+
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let line: String = line?;
+            let matches = scan_line_chimera(database, scratch, &line)?;
+            if matches.is_empty() {
+                continue;
+            }
+
+            if let Some(template) = &args.replace {
+                for m in &matches {
+                    println!("{}", apply_replace_template(template, &line, &m.groups));
+                }
+            } else if args.only_matching {
+                for m in &matches {
+                    if let Some(Some((from, to))) = m.groups.first() {
+                        // Same byte-offset-vs-char-boundary caveat as apply_replace_template.
+                        if let Some(text) = line.get(*from as usize..*to as usize) {
+                            println!("{text}");
+                        }
+                    }
+                }
+            } else {
+                println!("{line}");
+            }
+        }
+
+        unsafe {
+            ch::ch_free_scratch(scratch);
+            ch::ch_free_database(database);
         }
-    };
 
-    let database = compile_database(&pattern).context("compile pattern")?;
+        return Ok(());
+    }
+
+    let mode = if args.stream {
+        // Hyperscan requires a SOM horizon mode whenever HS_FLAG_SOMATCH is requested in
+        // streaming mode, or hs_compile_multi rejects the database outright.
+        hs::HS_MODE_STREAM | hs::HS_MODE_SOM_HORIZON_LARGE
+    } else {
+        hs::HS_MODE_BLOCK
+    };
+    let database =
+        compile_database(&args.patterns, mode, &args.compile_opts).context("compile patterns")?;
     let scratch = alloc_scratch(database).context("alloc scratch")?;
 
-    let stdin = io::stdin();
+    if args.stream {
+        // Read the whole input up front so match offsets can be mapped back to the
+        // lines they fall in once scanning completes.
+        let mut input = String::new();
+        io::stdin().lock().read_to_string(&mut input)?;
 
-    // Read lines from stdin and scan each line.
-    // Print lines that match the regex pattern.
-    for line in stdin.lock().lines() {
-        let line: String = line?;
-        if scan_line(database, scratch, &line)? {
+        let spans = scan_stream(database, scratch, &input)?;
+        for line in lines_for_spans(&input, &spans) {
             println!("{line}");
         }
+    } else {
+        let opts = OutputOptions {
+            show_ids: args.show_ids,
+            only_matching: args.only_matching,
+            byte_offset: args.byte_offset,
+            column: args.column,
+        };
+
+        if args.threads > 1 {
+            // Parallel scanning needs every line up front so batches can be handed out
+            // and reassembled in order; the serial path below instead streams line by line.
+            let lines: Vec<String> = io::stdin().lock().lines().collect::<io::Result<_>>()?;
+            for line in scan_lines_parallel(database, scratch, lines, args.threads, opts)? {
+                println!("{line}");
+            }
+        } else {
+            let stdin = io::stdin();
+
+            // Read lines from stdin and scan each line.
+            // Print lines that match any pattern, formatted per the requested output options.
+            for line in stdin.lock().lines() {
+                let line: String = line?;
+                let scan = scan_line(database, scratch, &line)?;
+                for out in opts.format(&line, &scan) {
+                    println!("{out}");
+                }
+            }
+        }
     }
 
     // Free Hyperscan resources we allocated.
@@ -169,3 +998,102 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(tokens: &[&str]) -> Result<Args> {
+        parse_args_from(tokens.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parse_args_rejects_unknown_flag() {
+        let err = args(&["--colum", "foo"]).unwrap_err();
+        assert!(err.to_string().contains("unknown flag"));
+    }
+
+    #[test]
+    fn parse_args_allows_pattern_looking_like_a_flag_dash() {
+        // A lone "-" is a valid pattern, not a flag.
+        assert!(args(&["-"]).is_ok());
+    }
+
+    #[test]
+    fn parse_args_rejects_combination_with_only_matching() {
+        let err = args(&["-e", "a", "-e", "b", "--combination", "0 AND 1", "--only-matching"])
+            .unwrap_err();
+        assert!(err.to_string().contains("--combination"));
+    }
+
+    #[test]
+    fn parse_args_rejects_pcre_with_stream() {
+        let err = args(&["--pcre", "--stream", "-e", "foo"]).unwrap_err();
+        assert!(err.to_string().contains("--pcre"));
+    }
+
+    #[test]
+    fn parse_args_rejects_pcre_with_threads() {
+        let err = args(&["--pcre", "--threads", "2", "-e", "foo"]).unwrap_err();
+        assert!(err.to_string().contains("--pcre"));
+    }
+
+    #[test]
+    fn lines_for_spans_includes_every_line_a_span_crosses() {
+        let input = "foo\nbar\nbaz";
+        // Span covering "foo\nbar" should surface both lines, not just the first.
+        let spans = [(0u64, 7u64)];
+        assert_eq!(lines_for_spans(input, &spans), vec!["foo", "bar"]);
+    }
+
+    #[test]
+    fn lines_for_spans_single_line_match() {
+        let input = "foo\nbar\nbaz";
+        let spans = [(4u64, 7u64)];
+        assert_eq!(lines_for_spans(input, &spans), vec!["bar"]);
+    }
+
+    #[test]
+    fn apply_replace_template_substitutes_groups() {
+        let line = "2026-07-29";
+        let groups = [Some((0, 10)), Some((0, 4)), Some((5, 7)), Some((8, 10))];
+        assert_eq!(apply_replace_template("$3/$2/$1", line, &groups), "29/07/2026");
+    }
+
+    #[test]
+    fn apply_replace_template_skips_non_char_boundary_span() {
+        let line = "café";
+        let groups = [Some((0, 5)), Some((3, 4))]; // group 1 splits the 'é'
+        assert_eq!(apply_replace_template("[$1]", line, &groups), "[]");
+    }
+
+    fn scan(spans: Vec<(u64, u64)>) -> LineScan {
+        LineScan { ids: vec![0], spans }
+    }
+
+    #[test]
+    fn format_only_matching_prints_each_span() {
+        let opts = OutputOptions {
+            show_ids: false,
+            only_matching: true,
+            byte_offset: false,
+            column: false,
+        };
+        let out = opts.format("foo bar foo", &scan(vec![(0, 3), (8, 11)]));
+        assert_eq!(out, vec!["foo", "foo"]);
+    }
+
+    #[test]
+    fn format_only_matching_falls_back_to_whole_line_off_char_boundary() {
+        let opts = OutputOptions {
+            show_ids: false,
+            only_matching: true,
+            byte_offset: false,
+            column: false,
+        };
+        // (3, 4) splits the multibyte 'é' in "café"; every span is unusable, so the
+        // whole line should still be printed rather than nothing.
+        let out = opts.format("café", &scan(vec![(3, 4)]));
+        assert_eq!(out, vec!["café"]);
+    }
+}